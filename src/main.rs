@@ -9,6 +9,9 @@ use structopt::StructOpt;
 
 use dla::{Dla, Vec3};
 
+mod render;
+mod scene_script;
+
 /// Simulate 3D diffusion limited aggregation (DLA for short) and save the final
 /// system as a scene ready to be rendered using povray for example.
 #[derive(StructOpt, Debug)]
@@ -27,11 +30,34 @@ struct App {
     #[structopt(short = "g", long = "spawn-radius", default_value = "10")]
     spawn_radius: u32,
 
-    /// The output formats the scene should be saved as. As of now `javascript,
-    /// `povray` and `csv` are supported.
+    /// The output formats the scene should be saved as. As of now `javascript`,
+    /// `povray`, `csv`, `png`, `gltf` and `ply` are supported.
     #[structopt(short = "s", long = "scene-format", default_value = "povray")]
     scene_formats: Vec<SceneFormat>,
 
+    /// Width in pixels of the image produced by the native `png` renderer.
+    #[structopt(long = "width", default_value = "800")]
+    width: u32,
+
+    /// Height in pixels of the image produced by the native `png` renderer.
+    #[structopt(long = "height", default_value = "800")]
+    height: u32,
+
+    /// Number of paths averaged per pixel by the native `png` renderer. Higher
+    /// values reduce noise from the Monte-Carlo global illumination.
+    #[structopt(long = "samples", default_value = "16")]
+    samples: u32,
+
+    /// Vertical field of view, in degrees, of the native `png` renderer.
+    #[structopt(long = "fov", default_value = "40")]
+    fov: f64,
+
+    /// A Lua script describing the scene to render. When given it drives camera,
+    /// lights and the distance-based color palette instead of the built-in
+    /// heuristics.
+    #[structopt(long = "scene-script", parse(from_os_str))]
+    scene_script: Option<PathBuf>,
+
     /// Output filename where to save the scene.
     #[structopt(parse(from_os_str), default_value = "dla.pov")]
     output: PathBuf,
@@ -42,25 +68,190 @@ enum SceneFormat {
     Povray,
     Js,
     Csv,
+    Png,
+    Gltf,
+    Ply,
 }
 
 #[derive(Debug)]
-struct Scene {
+pub(crate) struct Scene {
     camera: Camera,
     lights: Vec<Light>,
+    palette: Vec<ColorStop>,
     dla: Dla,
 }
 
 #[derive(Debug)]
-struct Camera {
+pub(crate) struct Camera {
     position: Vec3,
     target: Vec3,
 }
 
+/// A light in the scene. Point and spot lights have a physical position and
+/// inverse-square falloff; directional lights model an infinitely far parallel
+/// source (e.g. the sun) and only carry a direction.
 #[derive(Debug)]
-struct Light {
-    position: Vec3,
-    intensity: f32,
+pub(crate) enum Light {
+    Point {
+        position: Vec3,
+        intensity: f32,
+        /// Distance past which the light no longer contributes.
+        range: f32,
+    },
+    Spot {
+        position: Vec3,
+        direction: Vec3,
+        intensity: f32,
+        /// Half-angle (radians) of the outer cone, where the light fades to 0.
+        cone_angle: f32,
+        /// Half-angle (radians) of the inner cone, full intensity inside it.
+        penumbra_angle: f32,
+        /// Distance past which the light no longer contributes.
+        range: f32,
+    },
+    Directional {
+        direction: Vec3,
+        intensity: f32,
+    },
+}
+
+/// The result of [`Light::sample_ray`]: the point the light is emitted from
+/// (the far end of a shadow ray cast from the shaded point) and how much the
+/// light contributes once cone and distance attenuation are taken into account.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LightSample {
+    origin: Vec3,
+    attenuation: f32,
+}
+
+impl Light {
+    /// Build the shadow ray origin and attenuation factor for the point `toward`.
+    ///
+    /// Returns `None` for [`Light::Directional`], which has no finite origin and
+    /// must be handled as a parallel source by the caller. Point lights fall off
+    /// with inverse-square distance clamped by `range`; spot lights additionally
+    /// fade linearly between `penumbra_angle` and `cone_angle`.
+    fn sample_ray(&self, toward: Vec3) -> Option<LightSample> {
+        match *self {
+            Light::Directional { .. } => None,
+            Light::Point { position, intensity, range } => Some(LightSample {
+                origin: position,
+                attenuation: intensity * distance_attenuation(position, toward, range),
+            }),
+            Light::Spot { position, direction, intensity, cone_angle, penumbra_angle, range } => {
+                let (tx, ty, tz) = fvec(toward);
+                let (px, py, pz) = fvec(position);
+                let to_point = (tx - px, ty - py, tz - pz);
+                let spot = fvec(direction);
+                let cos = fdot(fnorm(to_point), fnorm(spot));
+                let angle = cos.max(-1.0).min(1.0).acos();
+
+                // linear smooth edge between the inner and outer cone
+                let cone = if angle <= penumbra_angle {
+                    1.0
+                } else if angle >= cone_angle {
+                    0.0
+                } else {
+                    (cone_angle - angle) / (cone_angle - penumbra_angle)
+                };
+
+                Some(LightSample {
+                    origin: position,
+                    attenuation: intensity * cone * distance_attenuation(position, toward, range),
+                })
+            }
+        }
+    }
+}
+
+/// Inverse-square distance attenuation between `from` and `to`, smoothly
+/// clamped to zero as the distance approaches `range`.
+fn distance_attenuation(from: Vec3, to: Vec3, range: f32) -> f32 {
+    let d2 = from.dist2(to) as f32;
+    let inv_sq = 1.0 / (1.0 + d2);
+    let window = if range.is_finite() {
+        (1.0 - d2.sqrt() / range).max(0.0)
+    } else {
+        1.0
+    };
+    inv_sq * window
+}
+
+fn fvec(v: Vec3) -> (f32, f32, f32) {
+    (v.x as f32, v.y as f32, v.z as f32)
+}
+
+fn fdot(a: (f32, f32, f32), b: (f32, f32, f32)) -> f32 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+fn fnorm(v: (f32, f32, f32)) -> (f32, f32, f32) {
+    let len = fdot(v, v).sqrt();
+    if len == 0.0 {
+        v
+    } else {
+        (v.0 / len, v.1 / len, v.2 / len)
+    }
+}
+
+/// A single stop of the distance-based color gradient: `distance_fraction` is
+/// how far the cell is from the center relative to the farthest cell (in
+/// `0..=1`) and `rgb` is the color to use at that distance.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ColorStop {
+    distance_fraction: f64,
+    rgb: (f64, f64, f64),
+}
+
+/// The palette used when no scene script overrides it, roughly reproducing the
+/// old hardcoded green-to-yellow gradient.
+pub(crate) fn default_palette() -> Vec<ColorStop> {
+    vec![
+        ColorStop { distance_fraction: 0.0, rgb: (0.0, 0.2, 0.01) },
+        ColorStop { distance_fraction: 0.5, rgb: (0.34, 0.7, 0.03) },
+        ColorStop { distance_fraction: 1.0, rgb: (0.85, 0.84, 0.0) },
+    ]
+}
+
+/// Linearly interpolate the palette at `f` (clamped to `0..=1`), returning the
+/// color to paint a cell that far away from the center.
+fn interpolate_palette(stops: &[ColorStop], f: f64) -> (f64, f64, f64) {
+    let f = f.max(0.0).min(1.0);
+
+    let lo = stops
+        .iter()
+        .rev()
+        .find(|s| s.distance_fraction <= f)
+        .unwrap_or(&stops[0]);
+    let hi = stops
+        .iter()
+        .find(|s| s.distance_fraction >= f)
+        .unwrap_or_else(|| stops.last().unwrap());
+
+    let span = hi.distance_fraction - lo.distance_fraction;
+    let t = if span <= 0.0 { 0.0 } else { (f - lo.distance_fraction) / span };
+
+    let lerp = |a: f64, b: f64| a + (b - a) * t;
+    (lerp(lo.rgb.0, hi.rgb.0), lerp(lo.rgb.1, hi.rgb.1), lerp(lo.rgb.2, hi.rgb.2))
+}
+
+/// The distance-gradient color of every cell, in `dla.cells()` order: the same
+/// palette-by-distance scheme `save_pov_scene` paints with, shared so the ply
+/// and glTF exporters match the povray look.
+fn cell_colors(scene: &Scene) -> Vec<(Vec3, (f64, f64, f64))> {
+    let center = scene.dla.bbox().center();
+    let cells = scene.dla.cells().copied().collect::<Vec<_>>();
+
+    let max_d = cells.iter().map(|c| center.dist2(*c)).max().unwrap_or(1) as f64;
+    let max_d = max_d.max(1.0);
+
+    cells
+        .into_iter()
+        .map(|c| {
+            let f = (center.dist2(c) as f64 / max_d).sqrt();
+            (c, interpolate_palette(&scene.palette, f))
+        })
+        .collect()
 }
 
 fn main() -> io::Result<()> {
@@ -105,7 +296,11 @@ It contains {} particles and its bounding box goes from
         dla.bbox().volume(),
     );
 
-    let scene = Scene::new(dla);
+    let scene = match &args.scene_script {
+        Some(script) => scene_script::load(script, dla)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?,
+        None => Scene::new(dla),
+    };
 
     let scene_formats = args.scene_formats.into_iter().collect::<HashSet<_>>();
 
@@ -114,13 +309,25 @@ It contains {} particles and its bounding box goes from
             SceneFormat::Povray => save_pov_scene(&args.output, &scene)?,
             SceneFormat::Js => save_js_scene(&args.output, &scene)?,
             SceneFormat::Csv => save_csv_scene(&args.output, &scene)?,
+            SceneFormat::Png => render::save_png_scene(
+                &args.output,
+                &scene,
+                render::Settings {
+                    width: args.width,
+                    height: args.height,
+                    samples: args.samples,
+                    fov: args.fov,
+                },
+            )?,
+            SceneFormat::Gltf => save_gltf_scene(&args.output, &scene)?,
+            SceneFormat::Ply => save_ply_scene(&args.output, &scene)?,
         }
     }
 
     Ok(())
 }
 
-fn save_pov_scene(path: &PathBuf, Scene { dla, camera, lights }: &Scene) -> io::Result<()> {
+fn save_pov_scene(path: &PathBuf, Scene { dla, camera, lights, palette }: &Scene) -> io::Result<()> {
     let path = path.with_extension("pov");
     let mut out = BufWriter::new(File::create(&path)?);
 
@@ -153,41 +360,65 @@ camera {{
         camera.target.x, camera.target.y, camera.target.z,
     )?;
 
+    let center = bbox.center();
+    let away_dist = {
+        let d = bbox.dimensions();
+        d.x.min(d.y).min(d.z)
+    };
     for light in lights {
-        #[rustfmt::skip]
-        writeln!(
-            out,
-            "light_source {{ <{}, {}, {}> color rgb <{}, {}, {}> }}",
-            light.position.x, light.position.y, light.position.z,
-            light.intensity, light.intensity, light.intensity
-        )?;
+        match *light {
+            Light::Point { position, intensity, range } => {
+                #[rustfmt::skip]
+                writeln!(
+                    out,
+                    "light_source {{ <{}, {}, {}> color rgb <{i}, {i}, {i}>{} }}",
+                    position.x, position.y, position.z, pov_fade(range), i = intensity,
+                )?;
+            }
+            Light::Spot { position, direction, intensity, cone_angle, penumbra_angle, range } => {
+                let point_at = position + direction;
+                #[rustfmt::skip]
+                writeln!(
+                    out,
+                    "light_source {{ <{}, {}, {}> color rgb <{i}, {i}, {i}> spotlight radius {} falloff {} point_at <{}, {}, {}>{} }}",
+                    position.x, position.y, position.z,
+                    penumbra_angle.to_degrees(), cone_angle.to_degrees(),
+                    point_at.x, point_at.y, point_at.z, pov_fade(range), i = intensity,
+                )?;
+            }
+            Light::Directional { direction, intensity } => {
+                // emulate a distant parallel source shining towards the center
+                let away = center - direction.normalized() * away_dist;
+                #[rustfmt::skip]
+                writeln!(
+                    out,
+                    "light_source {{ <{}, {}, {}> color rgb <{i}, {i}, {i}> parallel point_at <{}, {}, {}> }}",
+                    away.x, away.y, away.z, center.x, center.y, center.z, i = intensity,
+                )?;
+            }
+        }
     }
 
-    let center = bbox.center();
     let mut cells = dla.cells().map(|cc| (cc, center.dist2(*cc))).collect::<Vec<_>>();
     cells.sort_by_key(|(_, d)| *d);
 
     let max_d = cells.last().expect("empty dla, cannot happen since it should be seeded").1;
     let mut cells = cells.into_iter();
 
-    let gradients = 3;
-    let n = gradients * 2;
+    let n = 6usize;
     for i in 0..n {
         writeln!(out, "\nunion {{")?;
         for (p, _) in cells.by_ref().take_while(|(_, dd)| *dd <= (i + 1) * max_d / n) {
             writeln!(out, "  sphere {{ <{}, {}, {}>, 1 }}", p.x, p.y, p.z)?;
         }
 
-        let (r, g, b) = match 5 + i / gradients {
-            0..=2 => (0.27, 0.3, 0.02),
-            3..=4 => (0.0, 0.6, 0.02),
-            5 => (0.34, 0.7, 0.03),
-            6 => (0.85, 0.84, 0.00),
-            _ => unreachable!(),
-        };
-
-        let f = (1.0 + (i % gradients) as f64) / (gradients as f64);
-        let (r, g, b) = (r * f, g * f, b * f);
+        // color the band by the palette sampled at the middle of its distance
+        // range so that the gradient stays smooth regardless of how many stops
+        // the script provides. Bands split the squared distance evenly, so the
+        // band spans distance fractions sqrt(i/n)..sqrt((i+1)/n).
+        let lo_f = (i as f64 / n as f64).sqrt();
+        let hi_f = ((i + 1) as f64 / n as f64).sqrt();
+        let (r, g, b) = interpolate_palette(palette, (lo_f + hi_f) / 2.0);
 
         writeln!(
             out,
@@ -242,11 +473,25 @@ var DLA = {{
     )?;
 
     for light in lights {
-        writeln!(
-            out,
-            "        {{ position: {{ x: {}, y: {}, z: {} }}, intensity: {} }},",
-            light.position.x, light.position.y, light.position.z, light.intensity
-        )?;
+        match *light {
+            Light::Point { position, intensity, range } => writeln!(
+                out,
+                "        {{ type: \"point\", position: {{ x: {}, y: {}, z: {} }}, intensity: {}, range: {} }},",
+                position.x, position.y, position.z, intensity, js_range(range)
+            )?,
+            Light::Spot { position, direction, intensity, cone_angle, penumbra_angle, range } => writeln!(
+                out,
+                "        {{ type: \"spot\", position: {{ x: {}, y: {}, z: {} }}, direction: {{ x: {}, y: {}, z: {} }}, intensity: {}, cone_angle: {}, penumbra_angle: {}, range: {} }},",
+                position.x, position.y, position.z,
+                direction.x, direction.y, direction.z,
+                intensity, cone_angle, penumbra_angle, js_range(range)
+            )?,
+            Light::Directional { direction, intensity } => writeln!(
+                out,
+                "        {{ type: \"directional\", direction: {{ x: {}, y: {}, z: {} }}, intensity: {} }},",
+                direction.x, direction.y, direction.z, intensity
+            )?,
+        }
     }
 
     writeln!(
@@ -296,6 +541,300 @@ The positions (x,y,z) of all the cells that form the DLA have been saved as a CS
     Ok(())
 }
 
+fn save_ply_scene(path: &PathBuf, scene: &Scene) -> io::Result<()> {
+    let path = path.with_extension("ply");
+    let mut out = BufWriter::new(File::create(&path)?);
+
+    let colors = cell_colors(scene);
+
+    #[rustfmt::skip]
+    writeln!(
+        out,
+        "ply\n\
+         format ascii 1.0\n\
+         comment 3D DLA geometry - generated by github.com/d-dorazio/dla\n\
+         element vertex {}\n\
+         property float x\n\
+         property float y\n\
+         property float z\n\
+         property uchar red\n\
+         property uchar green\n\
+         property uchar blue\n\
+         end_header",
+        colors.len()
+    )?;
+
+    for (c, (r, g, b)) in &colors {
+        writeln!(
+            out,
+            "{} {} {} {} {} {}",
+            c.x,
+            c.y,
+            c.z,
+            channel(*r),
+            channel(*g),
+            channel(*b)
+        )?;
+    }
+
+    println!(
+        r#"## PLY Point Cloud
+
+The DLA cells have been saved as a colored PLY point cloud ({path}) ready to be
+opened in MeshLab, Blender or any point-cloud viewer.
+"#,
+        path = path.display()
+    );
+
+    Ok(())
+}
+
+fn save_gltf_scene(path: &PathBuf, scene: &Scene) -> io::Result<()> {
+    let path = path.with_extension("glb");
+    let mut out = BufWriter::new(File::create(&path)?);
+
+    let colors = cell_colors(scene);
+    let count = colors.len();
+
+    // binary buffer: all positions (VEC3 f32) followed by all colors (VEC3 f32)
+    let mut bin = Vec::with_capacity(count * 24);
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for (c, _) in &colors {
+        let p = [c.x as f32, c.y as f32, c.z as f32];
+        for (i, &pi) in p.iter().enumerate() {
+            min[i] = min[i].min(pi);
+            max[i] = max[i].max(pi);
+            bin.extend_from_slice(&pi.to_le_bytes());
+        }
+    }
+    let colors_offset = bin.len();
+    for (_, (r, g, b)) in &colors {
+        for v in &[*r as f32, *g as f32, *b as f32] {
+            bin.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+
+    let pos_len = colors_offset;
+    let col_len = bin.len() - colors_offset;
+
+    // camera looks from position towards target
+    let eye = [
+        scene.camera.position.x as f32,
+        scene.camera.position.y as f32,
+        scene.camera.position.z as f32,
+    ];
+    let target = [
+        scene.camera.target.x as f32,
+        scene.camera.target.y as f32,
+        scene.camera.target.z as f32,
+    ];
+    let cam_matrix = look_at_matrix(eye, target);
+
+    // KHR_lights_punctual light definitions and the nodes that place them
+    let mut light_defs = vec![];
+    let mut light_nodes = vec![];
+    for light in &scene.lights {
+        let (def, node) = gltf_light(light);
+        let idx = light_defs.len();
+        light_defs.push(def);
+        light_nodes.push(format!(
+            r#"{{ {}, "extensions": {{ "KHR_lights_punctual": {{ "light": {} }} }} }}"#,
+            node, idx
+        ));
+    }
+
+    // scene node list: mesh(0), camera(1), then one node per light
+    let mut node_indices = vec![0usize, 1];
+    node_indices.extend(2..2 + light_nodes.len());
+    let scene_nodes = node_indices.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", ");
+
+    let mut nodes = vec![
+        r#"{ "mesh": 0 }"#.to_string(),
+        format!(r#"{{ "camera": 0, "matrix": [{}] }}"#, matrix_json(&cam_matrix)),
+    ];
+    nodes.extend(light_nodes);
+
+    let json = format!(
+        r#"{{
+  "asset": {{ "version": "2.0", "generator": "github.com/d-dorazio/dla" }},
+  "extensionsUsed": ["KHR_lights_punctual"],
+  "scene": 0,
+  "scenes": [{{ "nodes": [{scene_nodes}] }}],
+  "nodes": [{nodes}],
+  "cameras": [{{ "type": "perspective", "perspective": {{ "yfov": 0.6981, "znear": 0.1 }} }}],
+  "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0, "COLOR_0": 1 }}, "mode": 0 }}] }}],
+  "accessors": [
+    {{ "bufferView": 0, "componentType": 5126, "count": {count}, "type": "VEC3", "min": [{min}], "max": [{max}] }},
+    {{ "bufferView": 1, "componentType": 5126, "count": {count}, "type": "VEC3" }}
+  ],
+  "bufferViews": [
+    {{ "buffer": 0, "byteOffset": 0, "byteLength": {pos_len}, "target": 34962 }},
+    {{ "buffer": 0, "byteOffset": {colors_offset}, "byteLength": {col_len}, "target": 34962 }}
+  ],
+  "buffers": [{{ "byteLength": {bin_len} }}],
+  "extensions": {{ "KHR_lights_punctual": {{ "lights": [{lights}] }} }}
+}}"#,
+        scene_nodes = scene_nodes,
+        nodes = nodes.join(", "),
+        count = count,
+        min = vec3_json(&min),
+        max = vec3_json(&max),
+        pos_len = pos_len,
+        colors_offset = colors_offset,
+        col_len = col_len,
+        bin_len = bin.len(),
+        lights = light_defs.join(", "),
+    );
+
+    // assemble the GLB container: header + JSON chunk + BIN chunk, each padded
+    // to a 4-byte boundary (JSON with spaces, BIN with zeros).
+    let mut json_bytes = json.into_bytes();
+    while json_bytes.len() % 4 != 0 {
+        json_bytes.push(b' ');
+    }
+    while bin.len() % 4 != 0 {
+        bin.push(0);
+    }
+
+    let total = 12 + 8 + json_bytes.len() + 8 + bin.len();
+    out.write_all(&0x4654_6C67u32.to_le_bytes())?; // "glTF"
+    out.write_all(&2u32.to_le_bytes())?;
+    out.write_all(&(total as u32).to_le_bytes())?;
+
+    out.write_all(&(json_bytes.len() as u32).to_le_bytes())?;
+    out.write_all(&0x4E4F_534Au32.to_le_bytes())?; // "JSON"
+    out.write_all(&json_bytes)?;
+
+    out.write_all(&(bin.len() as u32).to_le_bytes())?;
+    out.write_all(&0x004E_4942u32.to_le_bytes())?; // "BIN\0"
+    out.write_all(&bin)?;
+
+    println!(
+        r#"## glTF Scene
+
+The DLA has been saved as a binary glTF point cloud ({path}) carrying the camera
+and lights, directly loadable in Bevy, three.js or Blender.
+"#,
+        path = path.display()
+    );
+
+    Ok(())
+}
+
+/// Quantize a `0..=1` linear color channel to an 8-bit value.
+fn channel(c: f64) -> u8 {
+    (c.max(0.0).min(1.0) * 255.0).round() as u8
+}
+
+/// Column-major model matrix placing a camera at `eye` looking at `target`,
+/// following glTF's convention that the camera looks down its local -Z axis.
+fn look_at_matrix(eye: [f32; 3], target: [f32; 3]) -> [f32; 16] {
+    let z = normalize(sub(eye, target));
+    let x = normalize(cross([0.0, 1.0, 0.0], z));
+    let y = cross(z, x);
+    [
+        x[0], x[1], x[2], 0.0,
+        y[0], y[1], y[2], 0.0,
+        z[0], z[1], z[2], 0.0,
+        eye[0], eye[1], eye[2], 1.0,
+    ]
+}
+
+/// Build the `KHR_lights_punctual` definition and the placing node for a light.
+fn gltf_light(light: &Light) -> (String, String) {
+    match *light {
+        Light::Point { position, intensity, range } => (
+            format!(r#"{{ "type": "point", "intensity": {}{} }}"#, intensity, range_json(range)),
+            format!(
+                r#""translation": [{}, {}, {}]"#,
+                position.x, position.y, position.z
+            ),
+        ),
+        Light::Spot { position, direction, intensity, cone_angle, penumbra_angle, range } => {
+            let m = look_at_matrix(
+                [position.x as f32, position.y as f32, position.z as f32],
+                [
+                    (position.x + direction.x) as f32,
+                    (position.y + direction.y) as f32,
+                    (position.z + direction.z) as f32,
+                ],
+            );
+            (
+                format!(
+                    r#"{{ "type": "spot", "intensity": {}{}, "spot": {{ "innerConeAngle": {}, "outerConeAngle": {} }} }}"#,
+                    intensity, range_json(range), penumbra_angle, cone_angle
+                ),
+                format!(r#""matrix": [{}]"#, matrix_json(&m)),
+            )
+        }
+        Light::Directional { direction, intensity } => {
+            // the light shines along its local -Z, which look_at aligns to
+            // (target - eye), so aim the node straight down `direction`
+            let m = look_at_matrix([0.0, 0.0, 0.0], [direction.x as f32, direction.y as f32, direction.z as f32]);
+            (
+                format!(r#"{{ "type": "directional", "intensity": {} }}"#, intensity),
+                format!(r#""matrix": [{}]"#, matrix_json(&m)),
+            )
+        }
+    }
+}
+
+/// The povray ` fade_distance <x> fade_power 2` clause, or empty for an
+/// infinite range since povray's SDL has no token for an unbounded fade.
+fn pov_fade(range: f32) -> String {
+    if range.is_finite() {
+        format!(" fade_distance {} fade_power 2", range)
+    } else {
+        String::new()
+    }
+}
+
+/// The JS `range` value: the number when finite, otherwise the `Infinity`
+/// global so the emitted scene stays valid JavaScript.
+fn js_range(range: f32) -> String {
+    if range.is_finite() {
+        range.to_string()
+    } else {
+        "Infinity".to_string()
+    }
+}
+
+/// A `, "range": <x>` JSON fragment, or empty when the range is infinite since
+/// glTF treats an omitted `range` as an unbounded light.
+fn range_json(range: f32) -> String {
+    if range.is_finite() {
+        format!(r#", "range": {}"#, range)
+    } else {
+        String::new()
+    }
+}
+
+fn matrix_json(m: &[f32; 16]) -> String {
+    m.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn vec3_json(v: &[f32; 3]) -> String {
+    v.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if len == 0.0 {
+        v
+    } else {
+        [v[0] / len, v[1] / len, v[2] / len]
+    }
+}
+
 impl Scene {
     /// build a scene from a DLA with camera and lights in a completely
     /// arbitrary way.
@@ -313,10 +852,11 @@ impl Scene {
             target: Vec3::new(0, 0, 0),
         };
 
+        let range = (away_dist * 8) as f32;
         let mut lights = vec![];
         let mut add_light = |pt: Vec3, intensity| {
             let position = pt + (pt - scene_bbox.center()).normalized() * away_dist;
-            lights.push(Light { position, intensity })
+            lights.push(Light::Point { position, intensity, range })
         };
 
         // key light
@@ -356,7 +896,7 @@ impl Scene {
             0.5,
         );
 
-        Scene { camera, lights, dla }
+        Scene { camera, lights, palette: default_palette(), dla }
     }
 }
 
@@ -368,6 +908,9 @@ impl std::str::FromStr for SceneFormat {
             "povray" => Ok(SceneFormat::Povray),
             "javascript" | "js" => Ok(SceneFormat::Js),
             "csv" => Ok(SceneFormat::Csv),
+            "png" => Ok(SceneFormat::Png),
+            "gltf" | "glb" => Ok(SceneFormat::Gltf),
+            "ply" => Ok(SceneFormat::Ply),
             s => Err(format!("`{}` is not a valid scene format", s)),
         }
     }
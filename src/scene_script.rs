@@ -0,0 +1,217 @@
+//! Embedded Lua DSL to describe a scene.
+//!
+//! When the user passes `--scene-script foo.lua` the hardcoded camera/light
+//! heuristics in `Scene::new` are bypassed and the script is evaluated instead.
+//! The script is handed a few Rust-backed constructors (`vec3.new`, `camera`,
+//! `light`) and the computed `bbox` so it can place everything relative to the
+//! aggregate, and it returns a table `{ camera = , lights = { ... }, palette = { ... } }`.
+
+use std::fs;
+use std::path::Path;
+
+use mlua::{AnyUserData, Lua, Table, UserData, UserDataFields, Value};
+
+use dla::{Dla, Vec3};
+
+use crate::{default_palette, Camera, ColorStop, Light, Scene};
+
+/// A 3D vector exposed to Lua as `vec3.new(x, y, z)`. It carries floats so
+/// scripts can do fractional math; it is rounded back to the integer lattice
+/// when converted into a `dla::Vec3`.
+#[derive(Debug, Clone, Copy)]
+struct LuaVec3 {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+impl UserData for LuaVec3 {
+    fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("x", |_, this| Ok(this.x));
+        fields.add_field_method_get("y", |_, this| Ok(this.y));
+        fields.add_field_method_get("z", |_, this| Ok(this.z));
+    }
+}
+
+impl LuaVec3 {
+    fn from_vec3(v: Vec3) -> Self {
+        LuaVec3 { x: v.x as f64, y: v.y as f64, z: v.z as f64 }
+    }
+
+    fn to_vec3(self) -> Vec3 {
+        Vec3::new(self.x.round() as i64, self.y.round() as i64, self.z.round() as i64)
+    }
+}
+
+/// `camera{ position = , look_at = }`.
+#[derive(Debug, Clone, Copy)]
+struct LuaCamera {
+    position: LuaVec3,
+    target: LuaVec3,
+}
+
+impl UserData for LuaCamera {}
+
+/// A light built by the `light{ ... }` constructor. The optional `type` field
+/// selects the variant (defaulting to `"point"`); spot and directional lights
+/// additionally read `direction`, and cones/ranges fall back to sensible
+/// defaults when omitted.
+#[derive(Debug, Clone, Copy)]
+enum LuaLight {
+    Point { position: LuaVec3, intensity: f64, range: f64 },
+    Spot {
+        position: LuaVec3,
+        direction: LuaVec3,
+        intensity: f64,
+        cone_angle: f64,
+        penumbra_angle: f64,
+        range: f64,
+    },
+    Directional { direction: LuaVec3, intensity: f64 },
+}
+
+impl UserData for LuaLight {}
+
+impl LuaLight {
+    fn into_light(self) -> Light {
+        match self {
+            LuaLight::Point { position, intensity, range } => Light::Point {
+                position: position.to_vec3(),
+                intensity: intensity as f32,
+                range: range as f32,
+            },
+            LuaLight::Spot { position, direction, intensity, cone_angle, penumbra_angle, range } => {
+                Light::Spot {
+                    position: position.to_vec3(),
+                    direction: direction.to_vec3(),
+                    intensity: intensity as f32,
+                    cone_angle: cone_angle as f32,
+                    penumbra_angle: penumbra_angle as f32,
+                    range: range as f32,
+                }
+            }
+            LuaLight::Directional { direction, intensity } => Light::Directional {
+                direction: direction.to_vec3(),
+                intensity: intensity as f32,
+            },
+        }
+    }
+}
+
+/// Build a [`LuaLight`] from the table passed to the `light{ ... }` constructor.
+fn lua_light(t: Table) -> mlua::Result<LuaLight> {
+    let intensity: f64 = t.get("intensity")?;
+    let range = t.get::<_, Option<f64>>("range")?.unwrap_or(f64::INFINITY);
+    let kind: Option<String> = t.get("type")?;
+
+    match kind.as_deref() {
+        Some("point") | None => {
+            Ok(LuaLight::Point { position: vec3_field(&t, "position")?, intensity, range })
+        }
+        Some("spot") => Ok(LuaLight::Spot {
+            position: vec3_field(&t, "position")?,
+            direction: vec3_field(&t, "direction")?,
+            intensity,
+            cone_angle: t.get::<_, Option<f64>>("cone_angle")?.unwrap_or(std::f64::consts::FRAC_PI_6),
+            penumbra_angle: t.get::<_, Option<f64>>("penumbra_angle")?.unwrap_or(0.0),
+            range,
+        }),
+        Some("directional") => {
+            Ok(LuaLight::Directional { direction: vec3_field(&t, "direction")?, intensity })
+        }
+        Some(other) => Err(mlua::Error::RuntimeError(format!("unknown light type `{}`", other))),
+    }
+}
+
+/// Evaluate `path` as a scene script and build the `Scene` it describes.
+pub(crate) fn load(path: &Path, dla: Dla) -> mlua::Result<Scene> {
+    let src = fs::read_to_string(path).map_err(mlua::Error::external)?;
+
+    let lua = Lua::new();
+    let globals = lua.globals();
+
+    // vec3.new(x, y, z)
+    let vec3 = lua.create_table()?;
+    vec3.set(
+        "new",
+        lua.create_function(|_, (x, y, z): (f64, f64, f64)| Ok(LuaVec3 { x, y, z }))?,
+    )?;
+    globals.set("vec3", vec3)?;
+
+    // camera{ position = , look_at = }
+    globals.set(
+        "camera",
+        lua.create_function(|_, t: Table| {
+            Ok(LuaCamera { position: vec3_field(&t, "position")?, target: vec3_field(&t, "look_at")? })
+        })?,
+    )?;
+
+    // light{ type = , position = , direction = , intensity = , ... }
+    globals.set("light", lua.create_function(|_, t: Table| lua_light(t))?)?;
+
+    // expose the aggregate bounding box so scripts can frame relative to it
+    let bbox = dla.bbox();
+    let bbox_tbl = lua.create_table()?;
+    bbox_tbl.set("lower", LuaVec3::from_vec3(bbox.lower()))?;
+    bbox_tbl.set("upper", LuaVec3::from_vec3(bbox.upper()))?;
+    bbox_tbl.set("center", LuaVec3::from_vec3(bbox.center()))?;
+    globals.set("bbox", bbox_tbl)?;
+
+    let scene: Table = lua
+        .load(&src)
+        .set_name(path.to_string_lossy())
+        .eval()?;
+
+    let camera = {
+        let cam = userdata::<LuaCamera>(scene.get("camera")?)?;
+        Camera { position: cam.position.to_vec3(), target: cam.target.to_vec3() }
+    };
+
+    let mut lights = vec![];
+    for light in scene.get::<_, Table>("lights")?.sequence_values::<AnyUserData>() {
+        let l = *light?.borrow::<LuaLight>()?;
+        lights.push(l.into_light());
+    }
+
+    let mut palette = vec![];
+    if let Ok(stops) = scene.get::<_, Table>("palette") {
+        for stop in stops.sequence_values::<Table>() {
+            let stop = stop?;
+            let rgb: Table = stop.get("rgb")?;
+            let distance_fraction: f64 = stop.get("distance_fraction")?;
+            if !distance_fraction.is_finite() {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "palette stop has a non-finite distance_fraction ({})",
+                    distance_fraction
+                )));
+            }
+            palette.push(ColorStop {
+                distance_fraction,
+                rgb: (rgb.get(1)?, rgb.get(2)?, rgb.get(3)?),
+            });
+        }
+    }
+    if palette.is_empty() {
+        palette = default_palette();
+    }
+    palette.sort_by(|a, b| a.distance_fraction.total_cmp(&b.distance_fraction));
+
+    Ok(Scene { camera, lights, palette, dla })
+}
+
+/// Read a `vec3` userdata out of a Lua table field.
+fn vec3_field(t: &Table, key: &str) -> mlua::Result<LuaVec3> {
+    userdata::<LuaVec3>(t.get(key)?)
+}
+
+/// Borrow a copy of a userdata `T` out of a Lua value.
+fn userdata<T: UserData + Copy + 'static>(value: Value) -> mlua::Result<T> {
+    match value {
+        Value::UserData(ud) => Ok(*ud.borrow::<T>()?),
+        other => Err(mlua::Error::RuntimeError(format!(
+            "expected {} userdata, got {}",
+            std::any::type_name::<T>(),
+            other.type_name()
+        ))),
+    }
+}